@@ -0,0 +1,31 @@
+use std::hint::black_box;
+
+use criterion::{ Criterion, criterion_group, criterion_main };
+use cssparser_rs::{ tokenize_checked, tokenize_into };
+
+/// Lexes every rule of bootstrap on its own, like a CSS-in-JS runtime tokenizing many small
+/// strings, once with a fresh `Vec` per rule and once reusing one buffer.
+fn tokenize(c: &mut Criterion) {
+    let css = std::fs::read_to_string("bootstrap-4.css").expect("Failed to read CSS file");
+    let rules = css.split_inclusive('}').collect::<Vec<_>>();
+
+    c.bench_function("tokenize_checked", |b| {
+        b.iter(|| {
+            for rule in &rules {
+                let _ = black_box(tokenize_checked(rule));
+            }
+        })
+    });
+
+    c.bench_function("tokenize_into", |b| {
+        let mut tokens = Vec::new();
+        b.iter(|| {
+            for rule in &rules {
+                let _ = black_box(tokenize_into(rule, &mut tokens));
+            }
+        })
+    });
+}
+
+criterion_group!(benches, tokenize);
+criterion_main!(benches);
@@ -0,0 +1,15 @@
+//! Uses the lexer from a `no_std` crate. Defining a panic handler here fails with a duplicate
+//! lang item if anything `cssparser-rs` links without its `std` feature still pulls in `std`.
+
+#![no_std]
+
+use core::panic::PanicInfo;
+
+#[panic_handler]
+fn panic(_: &PanicInfo) -> ! {
+    loop {}
+}
+
+pub fn count_tokens(css: &str) -> usize {
+    cssparser_rs::tokenize_checked(css).map_or(0, |tokens| tokens.len())
+}
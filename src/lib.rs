@@ -1,20 +1,40 @@
-use logos::{Logos, Span};
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+use alloc::{borrow::Cow, format, string::String, vec::Vec};
+use logos::{FilterResult, Lexer, Logos, Span};
 
 pub type Error = (String, Span);
 
-pub type Result<T> = std::result::Result<T, Error>;
+pub type Result<T> = core::result::Result<T, Error>;
 
 /// All meaningful CSS tokens
-#[derive(Logos, Debug)]
+///
+/// Overlapping patterns are resolved by the longest match first, so `1e12` is one number
+/// rather than `1` followed by the identifier `e12`, and `1px2` is a number with the unit
+/// `px2`. Only when two patterns match the same slice does `priority` decide.
+#[derive(Logos, Clone, Copy, Debug, PartialEq)]
 #[logos(skip r"[ \t\r\n\f]+")]
+#[logos(subpattern escape = r"\\([0-9a-fA-F]{1,6}(\r\n|[ \t\n\r\f])?|[^0-9a-fA-F\r\n\f])")]
+#[logos(subpattern ident = r"(-?([a-zA-Z_]|(?&escape))|--)([a-zA-Z0-9_-]|(?&escape))*")]
+#[logos(subpattern attribute_name = r"(((?&ident)|\*)?\|)?(?&ident)")]
+#[logos(subpattern string = r#""([^"\\]|\\(.|\n))*"|'([^'\\]|\\(.|\n))*'"#)]
 pub enum Token<'s> {
-    #[regex(r"\[[^\]]+\]", |lex| lex.slice())]
+    /// An attribute selector: a name with an optional namespace prefix (`xlink|href`, `*|href`,
+    /// `|href`), optionally followed by an operator, an identifier or string value and an `i`/`s`
+    /// flag. A missing name or value (`[=x]`, `[type=]`) doesn't match and surfaces as a lexer
+    /// error.
+    #[regex(
+        r"\[\s*(?&attribute_name)\s*([~|^$*]?=\s*((?&ident)|(?&string))\s*([iIsS]\s*)?)?\]",
+        |lex| lex.slice()
+    )]
     AttributeSelector(&'s str),
 
     #[token("+")]
     AdjacentSiblingCombinator,
 
-    #[regex(r"\.[a-zA-Z_][a-zA-Z0-9_-]*", |lex| lex.slice())]
+    #[regex(r"\.(?&ident)", |lex| lex.slice())]
     ClassSelector(&'s str),
 
     #[token("~")]
@@ -23,25 +43,44 @@ pub enum Token<'s> {
     #[regex(r"#[0-9a-fA-F]{3}([0-9a-fA-F]{3})?", |lex| lex.slice())]
     HexColor(&'s str),
 
-    #[regex(r"#[a-zA-Z_][a-zA-Z0-9_-]*", |lex| lex.slice())]
+    #[regex(r"#(?&ident)", |lex| lex.slice())]
     IdSelector(&'s str),
 
+    #[token("*")]
+    UniversalSelector,
+
     #[regex(r"!important", |lex| lex.slice())]
     Important(&'s str),
 
-    #[regex(r"[a-zA-Z_][a-zA-Z0-9_-]*\s*", |lex| lex.slice().trim(), priority = 2)]
+    /// A non-standard bang marker such as `!default` or `!global` from preprocessor output.
+    /// `!important` always lexes as [`Token::Important`].
+    #[regex(r"!(?&ident)", |lex| lex.slice())]
+    Bang(&'s str),
+
+    /// An identifier, including vendor-prefixed (`-ms-flexbox`) and `--custom` ones. It never
+    /// starts with a digit, `.` or a `-` followed by a digit, so it can't overlap a number.
+    #[regex(r"(?&ident)\s*", |lex| lex.slice().trim(), priority = 2)]
     Value(&'s str),
 
-    #[regex(r":[a-zA-Z_][a-zA-Z0-9_-]*", |lex| lex.slice())]
+    /// A pseudo-class, including the raw argument of functional ones like `:lang(en-US)` or
+    /// `:not(.a)` (one level of nested parentheses is allowed).
+    #[regex(r":(?&ident)(\(([^()]|\([^()]*\))*\))?", |lex| lex.slice())]
     PseudoClass(&'s str),
 
-    #[regex(r"::[a-zA-Z_][a-zA-Z0-9_-]*", |lex| lex.slice())]
+    #[regex(r"::(?&ident)", |lex| lex.slice())]
     PseudoElement(&'s str),
 
+    /// A property name with its colon. A type selector followed by a pseudo-class (`a:hover`,
+    /// `p:lang(fr)`) lexes the same way, so outside a block this is a selector part.
     #[regex(r"[a-zA-Z-]+\s*:", |lex| lex.slice().trim_end_matches(':'))]
     Property(&'s str),
 
-    #[regex(r"[0-9]+(\.[0-9]+)?(px|em|rem|%)?", |lex| lex.slice())]
+    /// A number with an optional sign, fraction and exponent (`-.5`, `1.2e2`), followed by
+    /// `%` or any identifier as its unit (`10px`, `100vh`, `1fr`).
+    #[regex(
+        r"[+-]?([0-9]+(\.[0-9]+)?|\.[0-9]+)([eE][+-]?[0-9]+)?(%|(?&ident))?",
+        |lex| lex.slice()
+    )]
     NumericValue(&'s str),
 
     #[token("{")]
@@ -56,6 +95,15 @@ pub enum Token<'s> {
     #[regex(r"/\*[^*]*\*+(?:[^/*][^*]*\*+)*/", logos::skip)]
     Comment,
 
+    /// A comment left open at the end of the input. It runs to the end, like in browsers.
+    #[regex(r"/\*([^*]|\*+[^*/])*\**")]
+    BadComment,
+
+    /// A byte order mark is only skipped at the very start of the input, anywhere else it is an
+    /// error. Spans still count its three bytes.
+    #[token("\u{FEFF}", byte_order_mark)]
+    ByteOrderMark,
+
     #[token(":")]
     Colon,
 
@@ -65,10 +113,465 @@ pub enum Token<'s> {
     #[token(",")]
     Comma,
 
-    #[regex(r#""[^"]*""#, |lex| lex.slice())]
-    #[regex(r#"'[^']*'"#, |lex| lex.slice())]
+    /// The separator in values like `font: 12px/1.5` or `background: center/8px`.
+    #[token("/")]
+    Slash,
+
+    #[regex(r"(?&string)", |lex| lex.slice())]
     StringValue(&'s str),
 
-    #[regex(r"[a-zA-Z_][a-zA-Z0-9_-]*\([^)]*\)", |lex| lex.slice())]
+    /// A function call up to its matching `)`, so nested calls like `rgb(var(--r), 0, 0)` are
+    /// one token. A call still open at the next `{` or `}` is an error.
+    #[regex(r"(?&ident)\(", function)]
     Function(&'s str),
-}
\ No newline at end of file
+}
+
+fn function<'s>(lex: &mut Lexer<'s, Token<'s>>) -> FilterResult<&'s str, ()> {
+    let mut depth = 1;
+    for (i, c) in lex.remainder().char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' if depth == 1 => {
+                lex.bump(i + 1);
+                return FilterResult::Emit(lex.slice());
+            }
+            ')' => depth -= 1,
+            '{' | '}' => break,
+            _ => {}
+        }
+    }
+
+    FilterResult::Error(())
+}
+
+fn byte_order_mark<'s>(lex: &mut Lexer<'s, Token<'s>>) -> FilterResult<(), ()> {
+    if lex.span().start == 0 { FilterResult::Skip } else { FilterResult::Error(()) }
+}
+
+/// The kind of a [`Token`] without its slice, for callers that only need to tell tokens apart.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum TokenKind {
+    AttributeSelector,
+    AdjacentSiblingCombinator,
+    ClassSelector,
+    GeneralSiblingCombinator,
+    HexColor,
+    IdSelector,
+    UniversalSelector,
+    Important,
+    Bang,
+    Value,
+    PseudoClass,
+    PseudoElement,
+    Property,
+    NumericValue,
+    OpenBrace,
+    ChildCombinator,
+    CloseBrace,
+    Comment,
+    BadComment,
+    ByteOrderMark,
+    Colon,
+    Semicolon,
+    Comma,
+    Slash,
+    StringValue,
+    Function,
+}
+
+impl Token<'_> {
+    /// Returns the kind of this token.
+    pub fn kind(&self) -> TokenKind {
+        match self {
+            Token::AttributeSelector(_) => TokenKind::AttributeSelector,
+            Token::AdjacentSiblingCombinator => TokenKind::AdjacentSiblingCombinator,
+            Token::ClassSelector(_) => TokenKind::ClassSelector,
+            Token::GeneralSiblingCombinator => TokenKind::GeneralSiblingCombinator,
+            Token::HexColor(_) => TokenKind::HexColor,
+            Token::IdSelector(_) => TokenKind::IdSelector,
+            Token::UniversalSelector => TokenKind::UniversalSelector,
+            Token::Important(_) => TokenKind::Important,
+            Token::Bang(_) => TokenKind::Bang,
+            Token::Value(_) => TokenKind::Value,
+            Token::PseudoClass(_) => TokenKind::PseudoClass,
+            Token::PseudoElement(_) => TokenKind::PseudoElement,
+            Token::Property(_) => TokenKind::Property,
+            Token::NumericValue(_) => TokenKind::NumericValue,
+            Token::OpenBrace => TokenKind::OpenBrace,
+            Token::ChildCombinator => TokenKind::ChildCombinator,
+            Token::CloseBrace => TokenKind::CloseBrace,
+            Token::Comment => TokenKind::Comment,
+            Token::BadComment => TokenKind::BadComment,
+            Token::ByteOrderMark => TokenKind::ByteOrderMark,
+            Token::Colon => TokenKind::Colon,
+            Token::Semicolon => TokenKind::Semicolon,
+            Token::Comma => TokenKind::Comma,
+            Token::Slash => TokenKind::Slash,
+            Token::StringValue(_) => TokenKind::StringValue,
+            Token::Function(_) => TokenKind::Function,
+        }
+    }
+
+    /// Reports whether whitespace between this token and `next` changes the meaning of the CSS.
+    ///
+    /// The lexer skips whitespace, so consumers have to recover it from spans. It only matters
+    /// between two parts of a compound selector, where it is the descendant combinator (`div .a`
+    /// vs `div.a`). Next to explicit combinators, commas and braces it is insignificant.
+    ///
+    /// `in_selector` tells the two contexts apart, since the same pair of tokens can show up in
+    /// both: `solid #000` is a type selector and an id in a selector, but two words of a
+    /// `border` value. Inside declaration values whitespace only separates tokens the lexer has
+    /// already split, so it is never significant there.
+    pub fn is_whitespace_significant(&self, next: &Token, in_selector: bool) -> bool {
+        in_selector && self.is_selector_part() && next.is_selector_part()
+    }
+
+    fn is_selector_part(&self) -> bool {
+        matches!(
+            self,
+            Token::AttributeSelector(_)
+                | Token::ClassSelector(_)
+                | Token::HexColor(_)
+                | Token::IdSelector(_)
+                | Token::UniversalSelector
+                | Token::Value(_)
+                | Token::PseudoClass(_)
+                | Token::PseudoElement(_)
+                | Token::Property(_)
+        )
+    }
+}
+
+/// Lexes `css` into tokens paired with their spans, stopping at the first input the lexer
+/// doesn't recognize or at an unterminated comment. The error holds the offending slice (or
+/// "Unterminated comment", since that one runs to the end of the input) and its span.
+pub fn tokenize_checked(css: &str) -> Result<Vec<(Token<'_>, Span)>> {
+    let mut tokens = Vec::new();
+    tokenize_into(css, &mut tokens)?;
+
+    Ok(tokens)
+}
+
+/// Same as [`tokenize_checked`], but clears and fills `tokens` so a caller lexing many small
+/// inputs can reuse one allocation. On error `tokens` holds what was lexed before it.
+pub fn tokenize_into<'s>(css: &'s str, tokens: &mut Vec<(Token<'s>, Span)>) -> Result<()> {
+    let mut lexer = Token::lexer(css);
+    tokens.clear();
+
+    while let Some(token) = lexer.next() {
+        match token {
+            // an unterminated comment is not a lexer error, but still a mistake in the source
+            Ok(Token::BadComment) => {
+                return Err((String::from("Unterminated comment"), lexer.span()));
+            }
+            Err(()) => return Err((String::from(lexer.slice()), lexer.span())),
+            Ok(token) => tokens.push((token, lexer.span())),
+        }
+    }
+
+    Ok(())
+}
+
+/// Decodes the CSS escapes in an identifier, hash or string body (without its quotes).
+///
+/// A backslash followed by up to six hex digits is a code point, and one whitespace (`\r\n`
+/// counts as one) after it only ends the escape. Zero, surrogates and code points past U+10FFFF
+/// become U+FFFD. Any other escaped character stands for itself, so `a\.b` decodes to `a.b`,
+/// except an escaped newline, which only continues a string onto the next line and is removed.
+/// Input without a backslash is returned as is.
+pub fn unescape(css: &str) -> Cow<'_, str> {
+    if !css.contains('\\') {
+        return Cow::Borrowed(css);
+    }
+
+    let mut unescaped = String::with_capacity(css.len());
+    let mut chars = css.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+
+        let mut code_point = 0;
+        let mut digits = 0;
+        while let Some(digit) = chars.peek().and_then(|c| c.to_digit(16)) {
+            if digits == 6 {
+                break;
+            }
+            code_point = code_point * 16 + digit;
+            digits += 1;
+            chars.next();
+        }
+
+        if digits > 0 {
+            if chars.next_if(|c| matches!(c, ' ' | '\t' | '\n' | '\r' | '\x0C')) == Some('\r') {
+                chars.next_if_eq(&'\n');
+            }
+            unescaped.push(match char::from_u32(code_point) {
+                Some(c) if code_point != 0 => c,
+                _ => char::REPLACEMENT_CHARACTER,
+            });
+        } else if matches!(chars.peek(), Some('\n' | '\r' | '\x0C')) {
+            // an escaped newline in a string is a line continuation and is dropped
+            if chars.next() == Some('\r') && chars.peek() == Some(&'\n') {
+                chars.next();
+            }
+        } else {
+            // a backslash at the very end has nothing to escape
+            unescaped.push(chars.next().unwrap_or(char::REPLACEMENT_CHARACTER));
+        }
+    }
+
+    Cow::Owned(unescaped)
+}
+
+/// Renders `error` as a rustc-style snippet of `source`: the message, the line and column the
+/// span starts at, that line, and carets under the spanned text. A span running past the end
+/// of its line is underlined to the end of that line, and one starting or ending inside a
+/// character or past the end of `source` is widened or cut to whole characters. Tabs are shown
+/// as four spaces so the carets line up.
+pub fn render_codeframe(error: &Error, source: &str) -> String {
+    let (message, span) = error;
+    let mut start = span.start.min(source.len());
+    while !source.is_char_boundary(start) {
+        start -= 1;
+    }
+    let line_start = source[..start].rfind('\n').map_or(0, |i| i + 1);
+    let line_end = source[start..].find('\n').map_or(source.len(), |i| start + i);
+    let mut end = span.end.clamp(start, line_end);
+    while !source.is_char_boundary(end) {
+        end += 1;
+    }
+
+    let line_number = source[..start].matches('\n').count() + 1;
+    let column = source[line_start..start].chars().count() + 1;
+    let width = |text: &str| text.chars().map(|c| if c == '\t' { 4 } else { 1 }).sum::<usize>();
+
+    let gutter = " ".repeat(format!("{line_number}").len());
+    let line = source[line_start..line_end].trim_end_matches('\r').replace('\t', "    ");
+    let padding = " ".repeat(width(&source[line_start..start]));
+    let carets = "^".repeat(width(&source[start..end]).max(1));
+
+    format!(
+        "error: {message}\n{gutter}--> {line_number}:{column}\n{gutter} |\n{line_number} | {line}\n{gutter} | {padding}{carets}\n"
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lex(css: &str) -> Vec<Token<'_>> {
+        tokenize_checked(css).unwrap().into_iter().map(|(token, _)| token).collect()
+    }
+
+    #[track_caller]
+    fn assert_lex(css: &str, expected: &[Token]) {
+        assert_eq!(lex(css), expected, "lexing {css:?}");
+    }
+
+    #[test]
+    fn number_and_identifier_boundaries() {
+        assert_lex("1e12", &[Token::NumericValue("1e12")]);
+        assert_lex("1.5E-3%", &[Token::NumericValue("1.5E-3%")]);
+        assert_lex("e12", &[Token::Value("e12")]);
+        assert_lex("1px2", &[Token::NumericValue("1px2")]);
+        assert_lex("-.5em", &[Token::NumericValue("-.5em")]);
+        assert_lex("+.5", &[Token::NumericValue("+.5")]);
+        assert_lex("100vh", &[Token::NumericValue("100vh")]);
+        assert_lex("-ms-flexbox", &[Token::Value("-ms-flexbox")]);
+        assert_lex("--main-color", &[Token::Value("--main-color")]);
+        assert_lex("-1px -x", &[Token::NumericValue("-1px"), Token::Value("-x")]);
+    }
+
+    #[test]
+    fn tokenize_into_reuses_buffer() {
+        let mut tokens = Vec::new();
+
+        tokenize_into("a { color: red }", &mut tokens).unwrap();
+        assert_eq!(tokens, tokenize_checked("a { color: red }").unwrap());
+
+        let capacity = tokens.capacity();
+        tokenize_into("b{}", &mut tokens).unwrap();
+        assert_eq!(tokens, [
+            (Token::Value("b"), 0..1),
+            (Token::OpenBrace, 1..2),
+            (Token::CloseBrace, 2..3),
+        ]);
+        assert_eq!(tokens.capacity(), capacity);
+    }
+
+    #[test]
+    fn leading_byte_order_mark() {
+        assert_eq!(lex("\u{FEFF}a { color: red }"), lex("a { color: red }"));
+        assert_eq!(tokenize_checked("a\u{FEFF}").unwrap_err(), ("\u{FEFF}".into(), 1..4));
+    }
+
+    #[test]
+    fn control_character_error_span() {
+        let error = tokenize_checked("a { color: red\u{1} }").unwrap_err();
+
+        assert_eq!(error, ("\u{1}".into(), 14..15));
+    }
+
+    #[test]
+    fn multi_line_comment() {
+        assert_eq!(lex("a /* one\n * two\n */ { }"), lex("a { }"));
+        // comments don't nest, the first `*/` closes it
+        assert_eq!(lex("/* /* */ b"), [Token::Value("b")]);
+    }
+
+    #[test]
+    fn unterminated_comment() {
+        let error = tokenize_checked("a { }\n/* b {\n}").unwrap_err();
+
+        assert_eq!(error, ("Unterminated comment".into(), 6..14));
+    }
+
+    #[test]
+    fn escaped_class_name() {
+        assert_lex(r".a\.b", &[Token::ClassSelector(r".a\.b")]);
+        assert_eq!(unescape(r".a\.b"), ".a.b");
+    }
+
+    #[test]
+    fn escaped_code_point_in_string() {
+        assert_lex(r#"content: "\1F600""#, &[
+            Token::Property("content"),
+            Token::StringValue(r#""\1F600""#),
+        ]);
+        assert_eq!(unescape(r"\1F600"), "\u{1F600}");
+    }
+
+    #[test]
+    fn escaped_newline_in_string() {
+        assert_lex("\"a\\\nb\"", &[Token::StringValue("\"a\\\nb\"")]);
+        assert_eq!(unescape("a\\\nb"), "ab");
+        assert_eq!(unescape("a\\\r\nb \\\x0Cc"), "ab c");
+    }
+
+    #[test]
+    fn hex_escape_ends_at_any_newline() {
+        assert_lex(".\\41\r\nB", &[Token::ClassSelector(".\\41\r\nB")]);
+        assert_eq!(unescape("\\41\r\nB \\41\rB \\41\x0CB"), "AB AB AB");
+    }
+
+    #[test]
+    fn attribute_selectors() {
+        assert_lex("[type=x]", &[Token::AttributeSelector("[type=x]")]);
+        assert_lex("[lang|=en]", &[Token::AttributeSelector("[lang|=en]")]);
+        assert_lex("use[xlink|href]", &[
+            Token::Value("use"),
+            Token::AttributeSelector("[xlink|href]"),
+        ]);
+        assert_lex("[*|href]", &[Token::AttributeSelector("[*|href]")]);
+        assert_lex("[|href]", &[Token::AttributeSelector("[|href]")]);
+        assert!(tokenize_checked("[type=]").is_err());
+        assert!(tokenize_checked("[=x]").is_err());
+    }
+
+    #[test]
+    fn nested_function_calls() {
+        assert_lex("rgb(var(--r), 0, 0)", &[Token::Function("rgb(var(--r), 0, 0)")]);
+        assert_lex("url(data:a;b) x", &[Token::Function("url(data:a;b)"), Token::Value("x")]);
+        assert!(tokenize_checked("a { width: calc(1px }").is_err());
+    }
+
+    #[test]
+    fn slash_in_values() {
+        assert_lex("font: 0/0 a", &[
+            Token::Property("font"),
+            Token::NumericValue("0"),
+            Token::Slash,
+            Token::NumericValue("0"),
+            Token::Value("a"),
+        ]);
+    }
+
+    #[test]
+    fn bang_markers() {
+        assert_lex("color: red !default", &[
+            Token::Property("color"),
+            Token::Value("red"),
+            Token::Bang("!default"),
+        ]);
+        assert_lex("!important !global", &[Token::Important("!important"), Token::Bang("!global")]);
+    }
+
+    #[test]
+    fn token_kind() {
+        assert_eq!(Token::Value("x").kind(), TokenKind::Value);
+
+        let kinds = lex("a > * { color: #fff; }").iter().map(Token::kind).collect::<Vec<_>>();
+        assert_eq!(kinds, [
+            TokenKind::Value,
+            TokenKind::ChildCombinator,
+            TokenKind::UniversalSelector,
+            TokenKind::OpenBrace,
+            TokenKind::Property,
+            TokenKind::HexColor,
+            TokenKind::Semicolon,
+            TokenKind::CloseBrace,
+        ]);
+    }
+
+    #[test]
+    fn codeframe_in_multi_line_stylesheet() {
+        let source = "a {\n\tcolor: rgb(300, 0, 0);\n}\n";
+        let error = (String::from(r#"Invalid rgb component: "300""#), 12..26);
+
+        assert_eq!(
+            render_codeframe(&error, source),
+            concat!(
+                "error: Invalid rgb component: \"300\"\n",
+                " --> 2:9\n",
+                "  |\n",
+                "2 |     color: rgb(300, 0, 0);\n",
+                "  |            ^^^^^^^^^^^^^^\n",
+            )
+        );
+    }
+
+    #[test]
+    fn codeframe_span_inside_a_character() {
+        let error = (String::from("x"), 1..2);
+
+        assert_eq!(render_codeframe(&error, "é"), "error: x\n --> 1:1\n  |\n1 | é\n  | ^\n");
+        assert_eq!(render_codeframe(&error, ""), "error: x\n --> 1:1\n  |\n1 | \n  | ^\n");
+    }
+
+    #[test]
+    fn whitespace_in_selectors() {
+        let tokens = lex("div .a > p, b *");
+
+        // `div .a` and `b *` are descendants, `.a > p` and `p, b` are not
+        assert!(tokens[0].is_whitespace_significant(&tokens[1], true));
+        assert!(!tokens[1].is_whitespace_significant(&tokens[2], true));
+        assert!(!tokens[2].is_whitespace_significant(&tokens[3], true));
+        assert!(!tokens[3].is_whitespace_significant(&tokens[4], true));
+        assert!(!tokens[4].is_whitespace_significant(&tokens[5], true));
+        assert!(tokens[5].is_whitespace_significant(&tokens[6], true));
+    }
+
+    #[test]
+    fn pseudo_class_arguments() {
+        assert_eq!(lex(":lang(fr)"), [Token::PseudoClass(":lang(fr)")]);
+        assert_eq!(lex(":dir(ltr)"), [Token::PseudoClass(":dir(ltr)")]);
+        assert_eq!(lex("p:lang(fr)"), [Token::Property("p"), Token::Function("lang(fr)")]);
+    }
+
+    #[test]
+    fn whitespace_in_declaration_values() {
+        let tokens = lex("border: 1px solid #000");
+
+        assert_eq!(tokens[0], Token::Property("border"));
+        assert!(!tokens[0].is_whitespace_significant(&tokens[1], false));
+        assert!(!tokens[1].is_whitespace_significant(&tokens[2], false));
+        assert!(!tokens[2].is_whitespace_significant(&tokens[3], false));
+
+        // `solid #000` would be a descendant selector
+        assert_eq!(tokens[3], Token::HexColor("#000"));
+        assert!(tokens[2].is_whitespace_significant(&tokens[3], true));
+    }
+}
@@ -1,38 +1,44 @@
 #![allow(unused)]
 
 use core::fmt;
-use std::{ collections::HashMap, fmt::{ Display, Formatter }, fs::File, io::BufWriter };
+use std::{ fmt::{ Display, Formatter }, fs::File, io::BufWriter };
 use std::sync::{ Arc, Mutex, mpsc };
 use std::thread;
 use std::io::Write;
 
 use bumpalo::{ Bump, collections::String };
+use indexmap::IndexMap;
 
 use cssparser_rs::Token;
-use logos::{ Lexer, Logos };
+use logos::{ Lexer, Logos, Span };
 
 use rand::{ thread_rng, Rng };
 use rand::distributions::Alphanumeric;
 
 #[derive(Clone)]
 struct Parser<'a> {
-    selectors: HashMap<String<'a>, Properties<'a>>,
+    selectors: IndexMap<String<'a>, Properties<'a>>,
+    /// Mistakes the parser recovered from, in source order.
+    errors: Vec<cssparser_rs::Error>,
 }
 
 #[derive(Debug, Clone)]
 struct Properties<'a> {
-    properties: HashMap<String<'a>, Vec<String<'a>>>,
+    properties: IndexMap<String<'a>, Vec<String<'a>>>,
 }
 
 impl<'a> Parser<'a> {
     fn new() -> Self {
         Self {
-            selectors: HashMap::new(),
+            selectors: IndexMap::new(),
+            errors: Vec::new(),
         }
     }
 
+    /// Starts the rule for `selector`, or reopens it if it was seen before so a repeated
+    /// selector adds to its earlier declarations instead of replacing them.
     fn create_selector(&mut self, selector: String<'a>) {
-        self.selectors.insert(selector, Properties::new());
+        self.selectors.entry(selector).or_insert_with(Properties::new);
     }
 
     fn add_property(
@@ -41,14 +47,27 @@ impl<'a> Parser<'a> {
         property: String<'a>,
         value: Option<String<'a>>
     ) {
-        if self.selectors.contains_key(&selector) {
-            self.selectors.get_mut(&selector).unwrap().insert(property, value);
+        if let Some(properties) = self.selectors.get_mut(&selector) {
+            properties.insert(property, value);
         }
     }
 
     fn update_property(&mut self, selector: String<'a>, property: String<'a>, value: String<'a>) {
-        if self.selectors.contains_key(&selector) {
-            self.selectors.get_mut(&selector).unwrap().insert(property, Some(value));
+        if let Some(properties) = self.selectors.get_mut(&selector) {
+            properties.insert(property, Some(value));
+        }
+    }
+
+    /// Drops the values `property` got after its first `len`, and the property itself if that
+    /// leaves none, so an invalid declaration doesn't leave part of its value behind.
+    fn drop_values(&mut self, selector: &String<'a>, property: &String<'a>, len: usize) {
+        if let Some(properties) = self.selectors.get_mut(selector) {
+            if let Some(values) = properties.get_mut(property) {
+                values.truncate(len);
+                if values.is_empty() {
+                    properties.remove(property);
+                }
+            }
         }
     }
 }
@@ -56,25 +75,14 @@ impl<'a> Parser<'a> {
 impl<'a> Properties<'a> {
     fn new() -> Self {
         Self {
-            properties: HashMap::new(),
+            properties: IndexMap::new(),
         }
     }
 
     fn insert(&mut self, property: String<'a>, value: Option<String<'a>>) {
-        if self.properties.contains_key(&property) {
-            if let Some(prop) = self.properties.get_mut(&property) {
-                if let Some(value) = value {
-                    prop.push(value);
-                }
-            } else {
-                panic!("Failed to insert property: {:?} with value: {:?}", property, value);
-            }
-        } else {
-            if let Some(value) = value {
-                self.properties.insert(property, vec![value]);
-            } else {
-                self.properties.insert(property, vec![]);
-            }
+        let values = self.properties.entry(property).or_default();
+        if let Some(value) = value {
+            values.push(value);
         }
     }
 
@@ -87,7 +95,7 @@ impl<'a> Properties<'a> {
     }
 
     fn remove(&mut self, property: &String<'a>) -> Option<Vec<String<'a>>> {
-        self.properties.remove(property)
+        self.properties.shift_remove(property)
     }
 }
 
@@ -115,18 +123,31 @@ impl<'a> Properties<'a> {
 fn parse_chunk<'a>(chunk: &str, parser: &mut Parser<'a>, bump: &'a Bump) {
     let mut lexer = Token::lexer(chunk);
 
-    let mut current_selector = String::new_in(&bump);
-    let mut current_property = String::new_in(&bump);
-    let mut current_value = String::new_in(&bump);
+    let mut current_selector = String::new_in(bump);
+    let mut current_property = String::new_in(bump);
+    let mut current_value = String::new_in(bump);
+    let mut previous = None;
+    let mut in_block = false;
+    let mut invalid_selector = false;
+    let mut declaration_start = 0;
 
     while let Some(token) = lexer.next() {
+        // whitespace between two parts of a selector is the descendant combinator
+        if let (Some(previous), Ok(next)) = (&previous, &token) {
+            if Token::is_whitespace_significant(previous, next, !in_block)
+                && chunk[..lexer.span().start].ends_with(char::is_whitespace)
+            {
+                current_selector.push(' ');
+            }
+        }
+
         match token {
             Ok(Token::Value(value)) => {
                 if current_selector.is_empty() {
-                    current_selector = String::from_str_in(value, &bump);
+                    current_selector = String::from_str_in(value, bump);
                 } else if !current_property.is_empty() {
                     // replace current_value with value
-                    current_value = String::from_str_in(value, &bump);
+                    current_value = String::from_str_in(value, bump);
                     parser.update_property(
                         current_selector.clone(),
                         current_property.clone(),
@@ -144,27 +165,100 @@ fn parse_chunk<'a>(chunk: &str, parser: &mut Parser<'a>, bump: &'a Bump) {
             | Ok(Token::ClassSelector(value)) => {
                 current_selector.push_str(value);
             }
+            Ok(Token::Property(_)) if !current_property.is_empty() && current_value.is_empty() => {
+                // a value starting with a second `name:`, like IE's `progid:...` filters, is
+                // legacy syntax kept verbatim up to the end of the declaration
+                let start = lexer.span().start;
+                skip_declaration(&mut lexer);
+
+                current_value = String::from_str_in(
+                    chunk[start..lexer.span().end].trim_end(),
+                    bump
+                );
+                parser.update_property(
+                    current_selector.clone(),
+                    current_property.clone(),
+                    current_value.clone()
+                );
+            }
+            Ok(Token::Property(_)) | Err(_) if !current_property.is_empty() => {
+                // any other value the lexer can't read, like `..2px` or `1..2px`, is invalid
+                // and dropped
+                parser.errors.push((
+                    format!("Unexpected token: {:?}", lexer.slice()),
+                    lexer.span(),
+                ));
+                parser.drop_values(&current_selector, &current_property, declaration_start);
+                skip_declaration(&mut lexer);
+            }
+            Ok(Token::Property(_)) if !in_block => {
+                // `a:hover` lexes as a property, keep it verbatim in the selector
+                current_selector.push_str(lexer.slice());
+            }
+            Err(_) if !in_block => {
+                let message = if lexer.slice().starts_with('[') {
+                    // report the attribute selector as a whole, up to its `]`
+                    let rest = lexer.remainder();
+                    if let Some(len) = rest.find([']', '[', '{', '}']) {
+                        if rest[len..].starts_with(']') {
+                            lexer.bump(len + 1);
+                        }
+                    }
+                    format!("Invalid attribute selector: {:?}", lexer.slice())
+                } else {
+                    format!("Unexpected token: {:?}", lexer.slice())
+                };
+
+                // like in browsers an invalid selector drops the whole rule, only report the
+                // first mistake in it
+                if !invalid_selector {
+                    parser.errors.push((message, lexer.span()));
+                    invalid_selector = true;
+                }
+            }
             Ok(Token::Property(property)) => {
-                current_property = String::from_str_in(property, &bump);
+                current_property = String::from_str_in(property, bump);
                 parser.add_property(current_selector.clone(), current_property.clone(), None);
+                // an earlier declaration of the same property keeps its values if this one is
+                // dropped
+                declaration_start = parser.selectors
+                    .get(&current_selector)
+                    .and_then(|properties| properties.get(&current_property))
+                    .map_or(0, Vec::len);
+            }
+            Ok(Token::OpenBrace) if invalid_selector => {
+                // the block of an invalid selector, or of an at-rule like `@media` we don't
+                // support, is dropped as a whole, with any rules nested in it
+                skip_block(&mut lexer);
+                invalid_selector = false;
+                current_selector.clear();
             }
             Ok(Token::OpenBrace) => {
                 parser.create_selector(current_selector.clone());
+                in_block = true;
             }
             Ok(Token::CloseBrace) => {
+                in_block = false;
+                invalid_selector = false;
                 current_selector.clear();
                 current_property.clear();
                 current_value.clear();
             }
+            Ok(Token::Semicolon) if !in_block => {
+                // the end of an at-rule without a block, like `@import url(a.css);`
+                invalid_selector = false;
+                current_selector.clear();
+            }
             Ok(Token::Semicolon) => {
                 current_property.clear();
                 current_value.clear();
             }
             | Ok(Token::NumericValue(value))
             | Ok(Token::StringValue(value))
-            | Ok(Token::HexColor(value)) => {
+            | Ok(Token::HexColor(value))
+            | Ok(Token::Bang(value)) => {
                 if !current_property.is_empty() {
-                    current_value = String::from_str_in(value, &bump);
+                    current_value = String::from_str_in(value, bump);
                     parser.update_property(
                         current_selector.clone(),
                         current_property.clone(),
@@ -175,161 +269,55 @@ fn parse_chunk<'a>(chunk: &str, parser: &mut Parser<'a>, bump: &'a Bump) {
             Ok(Token::ChildCombinator) => {
                 current_selector.push('>');
             }
+            Ok(Token::UniversalSelector) => {
+                // `*zoom: 1` is an old IE hack inside a block, not a selector
+                if !in_block {
+                    current_selector.push('*');
+                }
+            }
             Ok(Token::Function(value)) => {
                 if !current_property.is_empty() {
-                    // check if the value is rgb or rgba if so, convert it to hex
-                    if value.starts_with("rgba") {
-                        let mut values = value
-                            .trim_start_matches("rgba(")
-                            .trim_end_matches(')')
-                            .split(',')
-                            .map(|v| v.trim())
-                            .collect::<Vec<&str>>();
-
-                        let hex = format!(
-                            "#{:02x}{:02x}{:02x}{:02x}",
-                            values[0].parse::<u8>().expect("Expected to parse red value"),
-                            values[1].parse::<u8>().expect("Expected to parse green value"),
-                            values[2].parse::<u8>().expect("Expected to parse blue value"),
-                            (
-                                values[3]
-                                    .parse::<f32>()
-                                    .expect("Expected to parse alpha value")
-                                    .clamp(0.0, 1.0) * 255.0
-                            ).round() as u8
-                        );
-                        current_value = String::from_str_in(hex.as_str(), &bump);
-                        parser.update_property(
-                            current_selector.clone(),
-                            current_property.clone(),
-                            current_value.clone()
-                        );
-                    } else if value.starts_with("rgb") {
-                        let mut values = value
-                            .trim_start_matches("rgb(")
-                            .trim_end_matches(')')
-                            .split(',')
-                            .map(|v| v.trim().parse::<u8>().unwrap())
-                            .collect::<Vec<u8>>();
-
-                        let hex = format!("#{:02x}{:02x}{:02x}", values[0], values[1], values[2]);
-                        current_value = String::from_str_in(hex.as_str(), &bump);
-                        parser.update_property(
-                            current_selector.clone(),
-                            current_property.clone(),
-                            current_value.clone()
-                        );
-                    } else if value.starts_with("hsla") {
-                        let mut values = value
-                            .trim_start_matches("hsla(")
-                            .trim_end_matches(')')
-                            .split(',')
-                            .map(|v| {
-                                let v = v.trim();
-                                if v.ends_with('%') {
-                                    v.trim_end_matches('%').parse::<f32>().unwrap()
-                                } else {
-                                    v.parse::<f32>().unwrap()
-                                }
-                            })
-                            .collect::<Vec<f32>>();
-
-                        let h = (values[0] % 360.0) / 360.0; // normalize h to 0-1 range
-                        let s = values[1] / 100.0;
-                        let l = values[2] / 100.0;
-                        let a = values[3].clamp(0.0, 1.0); // ensure alpha is between 0 and 1
-
-                        let c = (1.0 - (2.0 * l - 1.0).abs()) * s;
-                        let h_prime = h * 6.0;
-                        let x = c * (1.0 - ((h_prime % 2.0) - 1.0).abs());
-                        let m = l - c / 2.0;
-
-                        let (r, g, b) = if h_prime <= 1.0 {
-                            (c, x, 0.0)
-                        } else if h_prime <= 2.0 {
-                            (x, c, 0.0)
-                        } else if h_prime <= 3.0 {
-                            (0.0, c, x)
-                        } else if h_prime <= 4.0 {
-                            (0.0, x, c)
-                        } else if h_prime <= 5.0 {
-                            (x, 0.0, c)
-                        } else {
-                            (c, 0.0, x)
-                        };
-
-                        let r = ((r + m) * 255.0).round() as u8;
-                        let g = ((g + m) * 255.0).round() as u8;
-                        let b = ((b + m) * 255.0).round() as u8;
-
-                        let hex = format!("#{:02x}{:02x}{:02x}{:02x}", r, g, b, (a * 255.0) as u8);
-                        current_value = String::from_str_in(hex.as_str(), &bump);
-                        parser.update_property(
-                            current_selector.clone(),
-                            current_property.clone(),
-                            current_value.clone()
-                        );
-                    } else if value.starts_with("hsl") {
-                        let mut values = value
-                            .trim_start_matches("hsl(")
-                            .trim_end_matches(')')
-                            .split(',')
-                            .map(|v| {
-                                let v = v.trim();
-                                if v.ends_with('%') {
-                                    v.trim_end_matches('%').parse::<f32>().unwrap()
-                                } else {
-                                    v.parse::<f32>().unwrap()
-                                }
-                            })
-                            .collect::<Vec<f32>>();
-
-                        let h = (values[0] % 360.0) / 360.0; // normalize h to 0-1 range
-                        let s = values[1] / 100.0;
-                        let l = values[2] / 100.0;
-
-                        let c = (1.0 - (2.0 * l - 1.0).abs()) * s;
-                        let x = c * (1.0 - (((h * 6.0) % 2.0) - 1.0).abs());
-                        let m = l - c / 2.0;
-
-                        let (r, g, b) = if h < 1.0 / 6.0 {
-                            (c, x, 0.0)
-                        } else if h < 2.0 / 6.0 {
-                            (x, c, 0.0)
-                        } else if h < 3.0 / 6.0 {
-                            (0.0, c, x)
-                        } else if h < 4.0 / 6.0 {
-                            (0.0, x, c)
-                        } else if h < 5.0 / 6.0 {
-                            (x, 0.0, c)
-                        } else {
-                            (c, 0.0, x)
-                        };
-
-                        let r = ((r + m) * 255.0).round() as u8;
-                        let g = ((g + m) * 255.0).round() as u8;
-                        let b = ((b + m) * 255.0).round() as u8;
-
-                        let hex = format!("#{:02x}{:02x}{:02x}", r, g, b);
-                        current_value = String::from_str_in(hex.as_str(), &bump);
-                        parser.update_property(
-                            current_selector.clone(),
-                            current_property.clone(),
-                            current_value.clone()
-                        );
-                    } else {
-                        current_value = String::from_str_in(value, &bump);
-                        parser.update_property(
-                            current_selector.clone(),
-                            current_property.clone(),
-                            current_value.clone()
-                        );
+                    // rgb(a) and hsl(a) colors are converted to hex, other functions kept as is
+                    match color_to_hex(value, lexer.span()) {
+                        Ok(hex) => {
+                            let value = hex.as_deref().unwrap_or(value);
+                            current_value = String::from_str_in(value, bump);
+                            parser.update_property(
+                                current_selector.clone(),
+                                current_property.clone(),
+                                current_value.clone()
+                            );
+                        }
+                        Err(err) => {
+                            parser.errors.push(err);
+                            parser.drop_values(
+                                &current_selector,
+                                &current_property,
+                                declaration_start
+                            );
+                            skip_declaration(&mut lexer);
+                        }
                     }
+                } else if !in_block {
+                    // the argument of a pseudo-class after a type selector, as in `p:lang(fr)`
+                    current_selector.push_str(value);
                 }
             }
             Ok(Token::Comment) => {
                 // ignore comments
             }
+            Ok(Token::BadComment) => {
+                // an unterminated comment just runs to the end of the input, like in browsers
+                parser.errors.push(("Unterminated comment".into(), lexer.span()));
+            }
+            Ok(Token::Slash) if !current_property.is_empty() => {
+                current_value = String::from_str_in("/", bump);
+                parser.update_property(
+                    current_selector.clone(),
+                    current_property.clone(),
+                    current_value.clone()
+                );
+            }
             Ok(Token::Comma) => {
                 if current_property.is_empty() {
                     current_selector.push(',');
@@ -346,7 +334,193 @@ fn parse_chunk<'a>(chunk: &str, parser: &mut Parser<'a>, bump: &'a Bump) {
                 println!("{:?}", err.err()); */
             }
         }
+
+        previous = token.ok();
+    }
+}
+
+/// Moves `lexer` past the `}` that closes the block it is in, skipping nested blocks.
+fn skip_block<'s>(lexer: &mut Lexer<'s, Token<'s>>) {
+    let mut depth = 1;
+    for token in lexer.by_ref() {
+        match token {
+            Ok(Token::OpenBrace) => depth += 1,
+            Ok(Token::CloseBrace) if depth == 1 => return,
+            Ok(Token::CloseBrace) => depth -= 1,
+            _ => {}
+        }
+    }
+}
+
+/// Moves `lexer` to the `;` or `}` that ends the current declaration.
+fn skip_declaration<'s>(lexer: &mut Lexer<'s, Token<'s>>) {
+    let rest = lexer.remainder();
+    lexer.bump(rest.find([';', '}']).unwrap_or(rest.len()));
+}
+
+/// Converts an `rgb()`, `rgba()`, `hsl()` or `hsla()` color to hex. Other functions, and colors
+/// with a component like `var()` that is only known later, are kept as written (`None`).
+fn color_to_hex(function: &str, span: Span) -> cssparser_rs::Result<Option<std::string::String>> {
+    let is_rgb = function.starts_with("rgb");
+    if !is_rgb && !function.starts_with("hsl") {
+        return Ok(None);
+    }
+    if function.split_once('(').is_some_and(|(_, args)| args.contains('(')) {
+        return Ok(None);
+    }
+
+    let values = color_components(function, &span)?;
+    let mut hex = if is_rgb {
+        format!(
+            "#{:02x}{:02x}{:02x}",
+            parse_rgb_component(values[0], &span)?.unwrap_or(0),
+            parse_rgb_component(values[1], &span)?.unwrap_or(0),
+            parse_rgb_component(values[2], &span)?.unwrap_or(0)
+        )
+    } else {
+        let (r, g, b) = hsl_to_rgb(
+            parse_hue(values[0], &span)?.unwrap_or(0.0),
+            parse_hsl_component(values[1], &span)?.unwrap_or(0.0),
+            parse_hsl_component(values[2], &span)?.unwrap_or(0.0)
+        );
+        format!("#{:02x}{:02x}{:02x}", r, g, b)
+    };
+    if let Some(alpha) = values.get(3) {
+        let a = parse_alpha(alpha, &span)?.unwrap_or(0.0);
+        hex.push_str(&format!("{:02x}", (a * 255.0).round() as u8));
+    }
+
+    Ok(Some(hex))
+}
+
+/// Splits the arguments of a color function. Both the legacy comma-separated syntax and the
+/// space-separated `rgb(r g b / a)` syntax are accepted, but at least three components are
+/// required.
+fn color_components<'f>(function: &'f str, span: &Span) -> cssparser_rs::Result<Vec<&'f str>> {
+    let values = function
+        .split_once('(')
+        .map_or(function, |(_, args)| args)
+        .trim_end_matches(')')
+        .split(|c: char| c == ',' || c == '/' || c.is_whitespace())
+        .filter(|v| !v.is_empty())
+        .collect::<Vec<&str>>();
+
+    if values.len() < 3 {
+        return Err((
+            format!("Expected at least 3 color components: {:?}", function),
+            span.clone(),
+        ));
+    }
+
+    Ok(values)
+}
+
+/// Parses an rgb channel given as a number from 0 to 255 or a percentage, returning `None` for
+/// the `none` keyword (a missing component).
+fn parse_rgb_component(value: &str, span: &Span) -> cssparser_rs::Result<Option<u8>> {
+    if value == "none" {
+        return Ok(None);
+    }
+
+    let channel = if let Some(percentage) = value.strip_suffix('%') {
+        percentage.parse::<f32>().map(|percentage| percentage * 2.55)
+    } else {
+        value.parse::<f32>()
+    };
+
+    match channel {
+        Ok(channel) if (0.0..=255.0).contains(&channel) => Ok(Some(channel.round() as u8)),
+        _ => Err((format!("Invalid rgb component: {:?}", value), span.clone())),
+    }
+}
+
+/// Parses an hsl hue in degrees, with an optional `deg`, `grad`, `rad` or `turn` unit. Returns
+/// `None` for the `none` keyword.
+fn parse_hue(value: &str, span: &Span) -> cssparser_rs::Result<Option<f32>> {
+    if value == "none" {
+        return Ok(None);
+    }
+
+    let (number, scale) = if let Some(number) = value.strip_suffix("deg") {
+        (number, 1.0)
+    } else if let Some(number) = value.strip_suffix("grad") {
+        (number, 0.9)
+    } else if let Some(number) = value.strip_suffix("rad") {
+        (number, 180.0 / core::f32::consts::PI)
+    } else if let Some(number) = value.strip_suffix("turn") {
+        (number, 360.0)
+    } else {
+        (value, 1.0)
+    };
+
+    match number.parse::<f32>() {
+        Ok(hue) => Ok(Some(hue * scale)),
+        Err(_) => Err((format!("Invalid hsl component: {:?}", value), span.clone())),
+    }
+}
+
+/// Parses an hsl hue, saturation or lightness component, ignoring a trailing `%`. Returns
+/// `None` for the `none` keyword.
+fn parse_hsl_component(value: &str, span: &Span) -> cssparser_rs::Result<Option<f32>> {
+    if value == "none" {
+        return Ok(None);
+    }
+
+    match value.trim_end_matches('%').parse::<f32>() {
+        Ok(component) => Ok(Some(component)),
+        Err(_) => Err((format!("Invalid hsl component: {:?}", value), span.clone())),
+    }
+}
+
+/// Parses an alpha component given either as a number or a percentage, clamped to 0-1.
+/// Returns `None` for the `none` keyword.
+fn parse_alpha(value: &str, span: &Span) -> cssparser_rs::Result<Option<f32>> {
+    if value == "none" {
+        return Ok(None);
     }
+
+    let alpha = if let Some(percentage) = value.strip_suffix('%') {
+        percentage.parse::<f32>().map(|percentage| percentage / 100.0)
+    } else {
+        value.parse::<f32>()
+    };
+
+    match alpha {
+        Ok(alpha) => Ok(Some(alpha.clamp(0.0, 1.0))),
+        Err(_) => Err((format!("Invalid alpha value: {:?}", value), span.clone())),
+    }
+}
+
+/// Converts an hsl color to rgb. The hue is wrapped into the 0-360 range, so negative
+/// hues and hues past a full turn are accepted, and saturation/lightness are clamped to 0-100.
+fn hsl_to_rgb(h: f32, s: f32, l: f32) -> (u8, u8, u8) {
+    let h_prime = h.rem_euclid(360.0) / 60.0;
+    let s = s.clamp(0.0, 100.0) / 100.0;
+    let l = l.clamp(0.0, 100.0) / 100.0;
+
+    let c = (1.0 - (2.0 * l - 1.0).abs()) * s;
+    let x = c * (1.0 - ((h_prime % 2.0) - 1.0).abs());
+    let m = l - c / 2.0;
+
+    let (r, g, b) = if h_prime < 1.0 {
+        (c, x, 0.0)
+    } else if h_prime < 2.0 {
+        (x, c, 0.0)
+    } else if h_prime < 3.0 {
+        (0.0, c, x)
+    } else if h_prime < 4.0 {
+        (0.0, x, c)
+    } else if h_prime < 5.0 {
+        (x, 0.0, c)
+    } else {
+        (c, 0.0, x)
+    };
+
+    (
+        ((r + m) * 255.0).round() as u8,
+        ((g + m) * 255.0).round() as u8,
+        ((b + m) * 255.0).round() as u8,
+    )
 }
 
 fn main() {
@@ -358,6 +532,9 @@ fn main() {
 
     let start = std::time::Instant::now();
     parse_chunk(css.as_str(), &mut parser, &bump);
+    for err in &parser.errors {
+        eprint!("{}", cssparser_rs::render_codeframe(err, &css));
+    }
 
     /*     let elapsed = start.elapsed();
     println!("Elapsed: {:?}", elapsed);
@@ -370,7 +547,7 @@ fn main() {
 }
 
 /* fn minify(parser: Parser) -> String {
-    let mut property_map: HashMap<String, Vec<String>> = HashMap::new();
+    let mut property_map: IndexMap<String, Vec<String>> = IndexMap::new();
     let mut minified = String::new();
 
     // Collect selectors by their properties
@@ -396,3 +573,302 @@ fn main() {
 
     minified
 } */
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse<'a>(css: &str, bump: &'a Bump) -> Parser<'a> {
+        let mut parser = Parser::new();
+        parse_chunk(css, &mut parser, bump);
+        parser
+    }
+
+    #[test]
+    fn progid_filter_kept_raw() {
+        let bump = Bump::new();
+        let parser = parse(
+            "a { filter: progid:DXImageTransform.Microsoft.gradient(enabled=false); color: red }",
+            &bump
+        );
+
+        let properties = &parser.selectors["a"].properties;
+        assert_eq!(
+            properties["filter"],
+            ["progid:DXImageTransform.Microsoft.gradient(enabled=false)"]
+        );
+        assert_eq!(properties["color"], ["red"]);
+    }
+
+    #[test]
+    fn property_in_selector_does_not_open_a_value() {
+        let bump = Bump::new();
+        let parser = parse(".a tr:hover { color: red; width: 1px }", &bump);
+
+        let properties = &parser.selectors[".a tr:hover"].properties;
+        assert_eq!(properties["color"], ["red"]);
+        assert_eq!(properties["width"], ["1px"]);
+    }
+
+    #[test]
+    fn pseudo_class_after_type_selector() {
+        let bump = Bump::new();
+        let parser = parse(
+            "a:hover { color: red; width: 1px } p:lang(fr), div :dir(ltr) { x: 1 }",
+            &bump
+        );
+
+        let properties = &parser.selectors["a:hover"].properties;
+        assert_eq!(properties["color"], ["red"]);
+        assert_eq!(properties["width"], ["1px"]);
+        assert_eq!(parser.selectors["p:lang(fr),div :dir(ltr)"].properties["x"], ["1"]);
+    }
+
+    #[test]
+    fn selector_list_and_descendant() {
+        let bump = Bump::new();
+        let parser = parse("a, b { x: 1 } a b { y: 2 } a>b { z: 3 } a > * { w: 4 }", &bump);
+
+        let selectors = parser.selectors.keys().map(|s| s.as_str()).collect::<Vec<_>>();
+        assert_eq!(selectors, ["a,b", "a b", "a>b", "a>*"]);
+    }
+
+    #[test]
+    fn repeated_selector_is_coalesced() {
+        let bump = Bump::new();
+        let parser = parse(".a { color:red } .a { width:10px }", &bump);
+
+        assert_eq!(parser.selectors.len(), 1);
+        let properties = &parser.selectors[".a"].properties;
+        assert_eq!(properties["color"], ["red"]);
+        assert_eq!(properties["width"], ["10px"]);
+    }
+
+    #[test]
+    fn rgb_none_component() {
+        let bump = Bump::new();
+        let parser = parse("a { color: rgb(none 128 0) } b { color: hsl(none 100% 50%) }", &bump);
+
+        assert_eq!(parser.selectors["a"].properties["color"], ["#008000"]);
+        assert_eq!(parser.selectors["b"].properties["color"], ["#ff0000"]);
+    }
+
+    #[test]
+    fn legacy_rgb() {
+        let bump = Bump::new();
+        let parser = parse("a { color: rgb(255, 0, 0) } b { color: rgba(0, 0, 255, 0.5) }", &bump);
+
+        assert_eq!(parser.selectors["a"].properties["color"], ["#ff0000"]);
+        assert_eq!(parser.selectors["b"].properties["color"], ["#0000ff80"]);
+    }
+
+    #[test]
+    fn unterminated_comment_is_recorded() {
+        let bump = Bump::new();
+        let parser = parse("a { color: red }\n/* b { x: 1 }", &bump);
+
+        assert_eq!(parser.selectors.len(), 1);
+        assert_eq!(parser.selectors["a"].properties["color"], ["red"]);
+        assert_eq!(parser.errors, [("Unterminated comment".into(), 17..30)]);
+    }
+
+    #[test]
+    fn parse_order_is_stable() {
+        let css = "b { z: 1; a: 2 } a { y: 3; x: 4 }";
+        let dump = |parser: Parser| {
+            parser.selectors
+                .iter()
+                .map(|(selector, properties)| format!("{selector} {:?}", properties.properties))
+                .collect::<Vec<_>>()
+        };
+
+        let bump = Bump::new();
+        let first = dump(parse(css, &bump));
+        let second = dump(parse(css, &bump));
+
+        assert_eq!(first, second);
+        assert_eq!(first, [r#"b {"z": ["1"], "a": ["2"]}"#, r#"a {"y": ["3"], "x": ["4"]}"#]);
+    }
+
+    #[test]
+    fn invalid_attribute_selector() {
+        let bump = Bump::new();
+        let parser = parse("input[type=] { x: 1 } a[=x] { x: 2 } a[type=x] { x: 3 }", &bump);
+
+        assert_eq!(parser.selectors.len(), 1);
+        assert_eq!(parser.selectors["a[type=x]"].properties["x"], ["3"]);
+        assert_eq!(parser.errors, [
+            (r#"Invalid attribute selector: "[type=]""#.into(), 5..12),
+            (r#"Invalid attribute selector: "[=x]""#.into(), 23..27),
+        ]);
+    }
+
+    #[test]
+    fn unsupported_at_rule_drops_its_whole_block() {
+        let bump = Bump::new();
+        let parser = parse(
+            "@media (min-width: 1px) { .a { x: 1 } .b { y: 2 } } .c { z: 3 } \
+             @import url(a.css); .d { w: 4 }",
+            &bump
+        );
+
+        let selectors = parser.selectors.keys().map(|s| s.as_str()).collect::<Vec<_>>();
+        assert_eq!(selectors, [".c", ".d"]);
+        assert_eq!(parser.selectors[".c"].properties["z"], ["3"]);
+        assert_eq!(parser.selectors[".d"].properties["w"], ["4"]);
+        assert_eq!(parser.errors, [
+            (r#"Unexpected token: "@""#.into(), 0..1),
+            (r#"Unexpected token: "@""#.into(), 64..65),
+        ]);
+    }
+
+    #[test]
+    fn every_error_is_collected() {
+        let css = "a[=x] { x: 1 }\nb { y: 2 }\n/* c";
+        let bump = Bump::new();
+        let parser = parse(css, &bump);
+
+        assert_eq!(parser.selectors["b"].properties["y"], ["2"]);
+
+        let frames = parser.errors
+            .iter()
+            .map(|error| cssparser_rs::render_codeframe(error, css))
+            .collect::<Vec<_>>();
+        assert_eq!(frames, [
+            concat!(
+                "error: Invalid attribute selector: \"[=x]\"\n",
+                " --> 1:2\n  |\n1 | a[=x] { x: 1 }\n  |  ^^^^\n",
+            ),
+            "error: Unterminated comment\n --> 3:1\n  |\n3 | /* c\n  | ^^^^\n",
+        ]);
+    }
+
+    #[test]
+    fn invalid_color_drops_only_its_declaration() {
+        let bump = Bump::new();
+        let parser = parse(
+            "a { color: red; color: rgb(300, 0, 0); margin: 0 } b { width: 1px }",
+            &bump
+        );
+
+        assert_eq!(parser.selectors["a"].properties["color"], ["red"]);
+        assert_eq!(parser.selectors["a"].properties["margin"], ["0"]);
+        assert_eq!(parser.selectors["b"].properties["width"], ["1px"]);
+        assert_eq!(parser.errors, [(r#"Invalid rgb component: "300""#.into(), 23..37)]);
+    }
+
+    #[test]
+    fn malformed_number_is_an_error() {
+        let bump = Bump::new();
+        let parser = parse("a { width: 1..2px; height: 2px } b { width: ..2px }", &bump);
+
+        assert_eq!(parser.selectors["a"].properties.get("width"), None);
+        assert_eq!(parser.selectors["a"].properties["height"], ["2px"]);
+        assert_eq!(parser.selectors["b"].properties.get("width"), None);
+        assert_eq!(parser.errors, [
+            (r#"Unexpected token: ".""#.into(), 12..13),
+            (r#"Unexpected token: ".""#.into(), 44..45),
+        ]);
+    }
+
+    #[test]
+    fn colors_that_are_not_plain_numbers() {
+        let bump = Bump::new();
+        let parser = parse(
+            "a { color: rgb(100%, 0%, 0%) } b { color: hsl(120deg, 50%, 50%) } \
+             c { color: rgb(var(--r), 0, 0) }",
+            &bump
+        );
+
+        assert_eq!(parser.selectors["a"].properties["color"], ["#ff0000"]);
+        assert_eq!(parser.selectors["b"].properties["color"], ["#40bf40"]);
+        assert_eq!(parser.selectors["c"].properties["color"], ["rgb(var(--r), 0, 0)"]);
+        assert!(parser.errors.is_empty());
+    }
+
+    #[test]
+    fn hsl_negative_hue() {
+        let bump = Bump::new();
+        let parser = parse(
+            "a { color: hsl(-120, 50%, 50%) } b { color: hsl(240, 50%, 50%) }",
+            &bump
+        );
+
+        assert_eq!(parser.selectors["a"].properties["color"], ["#4040bf"]);
+        assert_eq!(parser.selectors["b"].properties["color"], ["#4040bf"]);
+    }
+
+    #[test]
+    fn hsla_hue_past_full_turn() {
+        let bump = Bump::new();
+        let parser = parse(
+            "a { color: hsla(400, 50%, 50%, 0.5) } b { color: hsla(40, 50%, 50%, 50%) }",
+            &bump
+        );
+
+        assert_eq!(parser.selectors["a"].properties["color"], ["#bf954080"]);
+        assert_eq!(parser.selectors["b"].properties["color"], ["#bf954080"]);
+    }
+
+    #[test]
+    fn numbers_are_stored_as_written() {
+        let bump = Bump::new();
+        let parser = parse("a { width: 0.50px; opacity: .5; margin: +1e1px }", &bump);
+
+        let properties = &parser.selectors["a"].properties;
+        assert_eq!(properties["width"], ["0.50px"]);
+        assert_eq!(properties["opacity"], [".5"]);
+        assert_eq!(properties["margin"], ["+1e1px"]);
+    }
+
+    #[test]
+    fn stray_semicolons_are_skipped() {
+        let bump = Bump::new();
+        let parser = parse("a { color: red;; width: 1px; ; } b { ;color: blue }", &bump);
+
+        let properties = &parser.selectors["a"].properties;
+        assert_eq!(properties.len(), 2);
+        assert_eq!(properties["color"], ["red"]);
+        assert_eq!(properties["width"], ["1px"]);
+        assert_eq!(parser.selectors["b"].properties["color"], ["blue"]);
+        assert!(parser.errors.is_empty());
+    }
+
+    #[test]
+    fn comments_in_selector_lists() {
+        let bump = Bump::new();
+        let parser = parse(
+            ".a, /* note */ .b { }\n.c,\n/* a\n   note */\n.d { } .e/**/.f { }",
+            &bump
+        );
+
+        let selectors = parser.selectors.keys().map(|s| s.as_str()).collect::<Vec<_>>();
+        assert_eq!(selectors, [".a,.b", ".c,.d", ".e.f"]);
+    }
+
+    #[test]
+    fn deep_nesting_does_not_overflow() {
+        let css = format!("{}{} b {{ x: 1 }}", "{".repeat(10_000), "}".repeat(10_000));
+        let bump = Bump::new();
+        let parser = parse(&css, &bump);
+
+        assert_eq!(parser.selectors["b"].properties["x"], ["1"]);
+
+        let css = format!("{}{} b {{ x: 1 }}", "@media { ".repeat(10_000), "}".repeat(10_000));
+        let parser = parse(&css, &bump);
+
+        assert_eq!(parser.selectors.len(), 1);
+        assert_eq!(parser.selectors["b"].properties["x"], ["1"]);
+    }
+
+    #[test]
+    fn css_wide_keywords() {
+        let bump = Bump::new();
+        let parser = parse("a { all: revert; color: revert-layer; width: inherit }", &bump);
+
+        let properties = &parser.selectors["a"].properties;
+        assert_eq!(properties["all"], ["revert"]);
+        assert_eq!(properties["color"], ["revert-layer"]);
+        assert_eq!(properties["width"], ["inherit"]);
+    }
+}
@@ -0,0 +1,16 @@
+use std::process::Command;
+
+/// Builds `no-std-check`, which depends on the crate with `--no-default-features`.
+#[test]
+fn builds_without_std() {
+    let root = env!("CARGO_MANIFEST_DIR");
+    let status = Command::new(env!("CARGO"))
+        .args(["build", "--quiet", "--manifest-path"])
+        .arg(format!("{root}/no-std-check/Cargo.toml"))
+        .arg("--target-dir")
+        .arg(format!("{root}/target/no-std-check"))
+        .status()
+        .expect("Failed to run cargo");
+
+    assert!(status.success());
+}